@@ -0,0 +1,310 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+use crate::source::{Clock, RandomSource};
+#[cfg(feature = "std")]
+use crate::source::{SystemClock, ThreadRng};
+
+use super::{BackwardsClockPolicy, TIME_BITS, TSID_EPOCH_MILLIS, TsidFactory};
+
+/// An epoch expressed either directly in milliseconds since the Unix epoch
+/// (always available) or as a `SystemTime` (`std` only, converted to millis
+/// when the factory is built).
+#[derive(Debug, Clone, Copy)]
+enum Epoch {
+    Millis(u64),
+    #[cfg(feature = "std")]
+    SystemTime(SystemTime),
+}
+
+/// Builds a [`TsidFactory`] with a custom epoch and/or time/node/counter bit
+/// split, validating the combination instead of panicking on a bad one.
+#[derive(Debug, Clone, Copy)]
+pub struct TsidFactoryBuilder {
+    time_bits: u8,
+    node_bits: u8,
+    counter_bits: u8,
+    epoch: Epoch,
+    node: u32,
+    backwards_clock_policy: BackwardsClockPolicy,
+}
+
+impl Default for TsidFactoryBuilder {
+    fn default() -> Self {
+        Self {
+            time_bits: TIME_BITS,
+            node_bits: 0,
+            counter_bits: 64 - TIME_BITS,
+            epoch: Epoch::Millis(TSID_EPOCH_MILLIS),
+            node: 0,
+            backwards_clock_policy: BackwardsClockPolicy::default(),
+        }
+    }
+}
+
+impl TsidFactoryBuilder {
+    /// Start from the crate's default layout: 42 time bits, 0 node bits, 22
+    /// counter bits, and the 2020-01-01 TSID epoch.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of bits used for the timestamp. Defaults to 42.
+    pub fn time_bits(mut self, time_bits: u8) -> Self {
+        self.time_bits = time_bits;
+        self
+    }
+
+    /// Number of bits used to identify the node. Defaults to 0.
+    pub fn node_bits(mut self, node_bits: u8) -> Self {
+        self.node_bits = node_bits;
+        self
+    }
+
+    /// Number of bits used for the per-millisecond counter. Defaults to 22.
+    pub fn counter_bits(mut self, counter_bits: u8) -> Self {
+        self.counter_bits = counter_bits;
+        self
+    }
+
+    /// Node identifier embedded in every generated `TSID`. Defaults to 0.
+    pub fn node(mut self, node: u32) -> Self {
+        self.node = node;
+        self
+    }
+
+    /// Custom epoch, as milliseconds since the Unix epoch, that generated
+    /// timestamps are measured from. Defaults to 2020-01-01T00:00:00Z.
+    /// Available without the `std` feature; see [`Self::epoch`] for a
+    /// `SystemTime`-based equivalent.
+    pub fn epoch_millis(mut self, epoch_millis: u64) -> Self {
+        self.epoch = Epoch::Millis(epoch_millis);
+        self
+    }
+
+    /// Custom epoch that generated timestamps are measured from. Defaults to
+    /// 2020-01-01T00:00:00Z.
+    #[cfg(feature = "std")]
+    pub fn epoch(mut self, epoch: SystemTime) -> Self {
+        self.epoch = Epoch::SystemTime(epoch);
+        self
+    }
+
+    /// What to do when the clock reports a time at or behind the last value
+    /// the factory used. Defaults to [`BackwardsClockPolicy::BumpSequence`].
+    pub fn backwards_clock_policy(mut self, policy: BackwardsClockPolicy) -> Self {
+        self.backwards_clock_policy = policy;
+        self
+    }
+
+    /// Build a [`TsidFactory`] using the default `std`-backed clock and RNG.
+    #[cfg(feature = "std")]
+    pub fn build(self) -> Result<TsidFactory<SystemClock, ThreadRng>, TsidFactoryBuildError> {
+        self.build_with_sources(SystemClock, ThreadRng)
+    }
+
+    /// Build a [`TsidFactory`] backed by a custom [`Clock`] and [`RandomSource`].
+    pub fn build_with_sources<C: Clock, R: RandomSource>(
+        self,
+        clock: C,
+        rng: R,
+    ) -> Result<TsidFactory<C, R>, TsidFactoryBuildError> {
+        let total_bits = self.time_bits as u16 + self.node_bits as u16 + self.counter_bits as u16;
+        if total_bits != 64 {
+            return Err(TsidFactoryBuildError::BitWidthMismatch {
+                time_bits: self.time_bits,
+                node_bits: self.node_bits,
+                counter_bits: self.counter_bits,
+            });
+        }
+
+        // `counter_bits` is used as a shift amount against 64-bit state (the
+        // packed counter and the node/counter split), so 64 itself (only
+        // reachable with `time_bits == 0, node_bits == 0`) would panic on the
+        // first `create()` rather than on a bad config up front.
+        if self.counter_bits >= 64 {
+            return Err(TsidFactoryBuildError::CounterBitsOverflow { counter_bits: self.counter_bits });
+        }
+
+        // Without `std`, `Epoch` only has the `Millis` variant, making this
+        // match infallible; keep it as a match anyway so enabling `std`
+        // doesn't require restructuring this code.
+        #[cfg_attr(not(feature = "std"), allow(clippy::infallible_destructuring_match))]
+        let epoch_millis = match self.epoch {
+            Epoch::Millis(millis) => millis,
+            #[cfg(feature = "std")]
+            Epoch::SystemTime(epoch) => epoch
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_err(|_| TsidFactoryBuildError::EpochBeforeUnixEpoch)?
+                .as_millis() as u64,
+        };
+
+        Ok(TsidFactory::from_parts(
+            self.time_bits,
+            self.node_bits,
+            self.counter_bits,
+            epoch_millis,
+            self.node,
+            clock,
+            rng,
+        )
+        .with_backwards_clock_policy(self.backwards_clock_policy))
+    }
+}
+
+/// Errors returned by [`TsidFactoryBuilder::build`]/[`TsidFactoryBuilder::build_with_sources`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsidFactoryBuildError {
+    /// `time_bits + node_bits + counter_bits` did not add up to 64.
+    BitWidthMismatch {
+        time_bits: u8,
+        node_bits: u8,
+        counter_bits: u8,
+    },
+    /// The configured epoch is before `SystemTime::UNIX_EPOCH`.
+    EpochBeforeUnixEpoch,
+    /// `counter_bits` is 64 or more, which would overflow a shift against the
+    /// 64-bit packed counter/node state on the first `create()` call.
+    CounterBitsOverflow { counter_bits: u8 },
+}
+
+impl fmt::Display for TsidFactoryBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TsidFactoryBuildError::BitWidthMismatch { time_bits, node_bits, counter_bits } => write!(
+                f,
+                "time_bits ({time_bits}) + node_bits ({node_bits}) + counter_bits ({counter_bits}) must equal 64"
+            ),
+            TsidFactoryBuildError::EpochBeforeUnixEpoch => {
+                write!(f, "epoch must not be before the Unix epoch")
+            }
+            TsidFactoryBuildError::CounterBitsOverflow { counter_bits } => {
+                write!(f, "counter_bits ({counter_bits}) must be less than 64")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TsidFactoryBuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_rejects_bit_width_mismatch() {
+        let result = TsidFactoryBuilder::new().time_bits(40).node_bits(0).counter_bits(22).build();
+        assert_eq!(
+            TsidFactoryBuildError::BitWidthMismatch {
+                time_bits: 40,
+                node_bits: 0,
+                counter_bits: 22,
+            },
+            result.unwrap_err()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_rejects_epoch_before_unix_epoch() {
+        let epoch = SystemTime::UNIX_EPOCH - std::time::Duration::from_millis(1);
+        let result = TsidFactoryBuilder::new().epoch(epoch).build();
+        assert_eq!(TsidFactoryBuildError::EpochBeforeUnixEpoch, result.unwrap_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_succeeds_with_custom_epoch_and_split() {
+        let factory = TsidFactoryBuilder::new()
+            .time_bits(40)
+            .node_bits(10)
+            .counter_bits(14)
+            .node(7)
+            .epoch(SystemTime::UNIX_EPOCH)
+            .build();
+        assert!(factory.is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_succeeds_with_epoch_millis() {
+        let factory = TsidFactoryBuilder::new().epoch_millis(0).build();
+        assert!(factory.is_ok());
+    }
+
+    #[test]
+    fn build_with_sources_succeeds_with_epoch_millis() {
+        struct FixedClock;
+        impl Clock for FixedClock {
+            fn now_unix_millis(&self) -> u64 {
+                0
+            }
+        }
+        struct FixedRng;
+        impl RandomSource for FixedRng {
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+        }
+
+        let factory = TsidFactoryBuilder::new().epoch_millis(0).build_with_sources(FixedClock, FixedRng);
+        assert!(factory.is_ok());
+    }
+
+    #[test]
+    fn build_rejects_counter_bits_overflow() {
+        #[derive(Debug)]
+        struct FixedClock;
+        impl Clock for FixedClock {
+            fn now_unix_millis(&self) -> u64 {
+                0
+            }
+        }
+        #[derive(Debug)]
+        struct FixedRng;
+        impl RandomSource for FixedRng {
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+        }
+
+        let result = TsidFactoryBuilder::new()
+            .time_bits(0)
+            .node_bits(0)
+            .counter_bits(64)
+            .build_with_sources(FixedClock, FixedRng);
+        assert_eq!(TsidFactoryBuildError::CounterBitsOverflow { counter_bits: 64 }, result.unwrap_err());
+    }
+
+    #[test]
+    fn create_does_not_overflow_with_wide_counter_bits() {
+        // A layout this builder accepts (`time_bits + node_bits + counter_bits
+        // == 64`) can still push `counter_bits` past 32, which used to panic
+        // on `(self.node as u32) << self.counter_bits` in `TsidFactory::create`
+        // even with the default `node == 0`.
+        struct FixedClock;
+        impl Clock for FixedClock {
+            fn now_unix_millis(&self) -> u64 {
+                TSID_EPOCH_MILLIS + 1
+            }
+        }
+        struct FixedRng;
+        impl RandomSource for FixedRng {
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+        }
+
+        let mut factory = TsidFactoryBuilder::new()
+            .time_bits(14)
+            .node_bits(10)
+            .counter_bits(40)
+            .build_with_sources(FixedClock, FixedRng)
+            .expect("valid 64-bit split");
+
+        assert!(factory.create().is_ok());
+    }
+}
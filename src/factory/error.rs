@@ -0,0 +1,33 @@
+use core::fmt;
+
+/// Errors that can occur while generating a `TSID`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsidGenerationError {
+    /// The clock reported a time earlier than the factory's configured epoch.
+    ClockBehindEpoch,
+    /// The time field is exhausted: no more milliseconds can be represented
+    /// under the current epoch and time-bit width.
+    TimeBitsExhausted,
+    /// The per-millisecond counter overflowed and advancing the time field to
+    /// make room would itself exhaust the time field.
+    CounterOverflowWithoutRoom,
+}
+
+impl fmt::Display for TsidGenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TsidGenerationError::ClockBehindEpoch => {
+                write!(f, "clock reports a time before the factory's epoch")
+            }
+            TsidGenerationError::TimeBitsExhausted => {
+                write!(f, "the time field is exhausted for the current epoch and bit width")
+            }
+            TsidGenerationError::CounterOverflowWithoutRoom => {
+                write!(f, "counter overflowed and there is no room left to advance the time field")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TsidGenerationError {}
@@ -1,28 +1,94 @@
+mod builder;
+mod error;
+
+#[cfg(feature = "std")]
 use std::ops::Add;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
 use std::time::{Duration, SystemTime};
+#[cfg(feature = "std")]
 use rand::RngCore;
-use crate::tsid::TSID;
+use crate::source::{Clock, RandomSource};
+#[cfg(feature = "std")]
+use crate::source::{SystemClock, ThreadRng};
+use crate::tsid::{TSID, TsidComponents};
+
+pub use builder::{TsidFactoryBuildError, TsidFactoryBuilder};
+pub use error::TsidGenerationError;
+
+pub(crate) const TIME_BITS: u8 = 42;
+pub(crate) const RANDOM_BITS: u8 = 64 - TIME_BITS;
+pub(crate) const TSID_EPOCH_MILLIS: u64 = 1577836800000;
+
+/// Mask with the lowest `bits` bits set.
+pub(crate) fn mask(bits: u8) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << bits) - 1
+    }
+}
+
+/// What to do when the clock reports a time at or behind the last value this
+/// factory used.
+///
+/// A regression can be a real clock step-back (e.g. NTP correction) or simply
+/// two calls landing in the same millisecond. Either way the factory must not
+/// emit a smaller or duplicate TSID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackwardsClockPolicy {
+    /// Keep advancing the counter under the last-used time value, bumping
+    /// that value forward by one only if the counter itself overflows. Never
+    /// blocks, so this is the default.
+    #[default]
+    BumpSequence,
+    /// Busy-loop re-reading the clock while it is strictly behind the
+    /// last-used time value. A reading equal to that value is not waited on:
+    /// the counter advances as under `BumpSequence`. A clock that stays
+    /// behind the last-used time value (e.g. a fixed clock set back in a
+    /// test) makes `create` hang.
+    SpinWait,
+}
 
-const TIME_BITS: u8 = 42;
-const RANDOM_BITS: u8 = 64 - TIME_BITS;
-const RANDOM_MASK: u64 = 0x003fffff;
-//22 bits
-const TSID_EPOCH_MILLIS: u64 = 1577836800000;
+#[cfg(feature = "std")]
+pub(crate) fn get_time_millis_in_tsid_epoch() -> Result<u128, TsidGenerationError> {
+    let tsid_epoch = SystemTime::UNIX_EPOCH.add(Duration::from_millis(TSID_EPOCH_MILLIS));
 
+    SystemTime::now()
+        .duration_since(tsid_epoch)
+        .map(|duration| duration.as_millis())
+        .map_err(|_| TsidGenerationError::ClockBehindEpoch)
+}
+
+/// Generates [`TSID`]s from a pluggable [`Clock`] and [`RandomSource`].
+///
+/// Under the `std` feature, [`TsidFactory::new`]/[`TsidFactory::with_node_bits`]
+/// give you the `std`-backed [`SystemClock`] and [`ThreadRng`], preserving the
+/// crate's original `SystemTime`/`thread_rng` behavior. On `no_std` targets
+/// (e.g. embedded, with a hardware tick counter) or in deterministic tests,
+/// supply your own `C`/`R` via [`TsidFactory::with_node_bits_and_sources`] or
+/// [`TsidFactoryBuilder::build_with_sources`].
 #[derive(Debug)]
-pub struct TsidFactory {
+pub struct TsidFactory<C, R> {
     // TODO: Consider if all of those can be generic constants
     node_bits: u8,
     counter_bits: u8,
+    random_bits: u8,
     counter_mask: u64,
-    node_mask: u64,
+    time_mask: u128,
+    epoch_millis: u64,
+    backwards_clock_policy: BackwardsClockPolicy,
     last_time_value: u128,
     counter: u64,
     node: u32,
+    clock: C,
+    rng: R,
 }
 
 
-impl Default for TsidFactory {
+#[cfg(feature = "std")]
+impl Default for TsidFactory<SystemClock, ThreadRng> {
     #[doc = "Create default TsidFactory with `node_bits: 0`"]
     fn default() -> Self {
         TsidFactory::with_node_bits(0, 0)
@@ -34,7 +100,8 @@ impl Default for TsidFactory {
 /// use tsid::TsidFactory;
 /// let factory = TsidFactory::with_node_bits(8,1);
 ///```
-impl TsidFactory {
+#[cfg(feature = "std")]
+impl TsidFactory<SystemClock, ThreadRng> {
     /// Create a new TsidFactory with default settings
     /// see [`TsidFactory::default`]
     pub fn new() -> Self {
@@ -42,69 +109,272 @@ impl TsidFactory {
     }
 
     pub fn with_node_bits(node_bits: u8, node: u32) -> Self {
+        TsidFactory::with_node_bits_and_sources(node_bits, node, SystemClock, ThreadRng)
+    }
+}
+
+impl<C: Clock, R: RandomSource> TsidFactory<C, R> {
+    /// Create a TsidFactory backed by a custom [`Clock`] and [`RandomSource`],
+    /// e.g. for `no_std` targets or deterministic tests.
+    pub fn with_node_bits_and_sources(node_bits: u8, node: u32, clock: C, rng: R) -> Self {
         let counter_bits: u8 = RANDOM_BITS - node_bits;
-        let counter_mask = RANDOM_MASK >> node_bits;
-        let node_mask = RANDOM_MASK >> counter_bits;
+        Self::from_parts(TIME_BITS, node_bits, counter_bits, TSID_EPOCH_MILLIS, node, clock, rng)
+    }
+
+    pub(crate) fn from_parts(
+        time_bits: u8,
+        node_bits: u8,
+        counter_bits: u8,
+        epoch_millis: u64,
+        node: u32,
+        clock: C,
+        mut rng: R,
+    ) -> Self {
+        let random_bits = 64 - time_bits;
+        let counter_mask = mask(counter_bits);
+        let time_mask = mask(time_bits) as u128;
 
-        let mut rng = rand::thread_rng();
         let counter = rng.next_u64() & counter_mask;
-        let last_time_value = Self::get_time_millis_in_tsid_epoch();
+        let last_time_value = clock.now_unix_millis().saturating_sub(epoch_millis) as u128;
 
         Self {
             node_bits,
             counter_bits,
+            random_bits,
             counter_mask,
-            node_mask,
+            time_mask,
+            epoch_millis,
+            backwards_clock_policy: BackwardsClockPolicy::default(),
             last_time_value,
             counter,
             node,
+            clock,
+            rng,
         }
     }
 
+    pub(crate) fn with_backwards_clock_policy(mut self, policy: BackwardsClockPolicy) -> Self {
+        self.backwards_clock_policy = policy;
+        self
+    }
 
     // naive implementation without thread safety
-    pub fn create(&mut self) -> TSID {
-        let time = self.get_time_and_advance_counter();
-        let node_val: u64 = (self.node << self.counter_bits) as u64;
-        let time_val: u64 = (time << RANDOM_BITS) as u64;
+    pub fn create(&mut self) -> Result<TSID, TsidGenerationError> {
+        let time = self.get_time_and_advance_counter()?;
+        let node_val: u64 = (self.node as u64) << self.counter_bits;
+        let time_val: u64 = (time << self.random_bits) as u64;
         let counter_val = self.counter & self.counter_mask;
         let number = time_val | node_val | counter_val;
-        TSID::new(number)
+        Ok(TSID::new(number))
     }
 
-    fn get_time_and_advance_counter(&mut self) -> u128 {
-        let mut rng = rand::thread_rng();
-        let mut time_millis = Self::get_time_millis_in_tsid_epoch();
+    /// Decompose a `TSID` produced by this factory back into its timestamp,
+    /// node, and counter, using this factory's own epoch and bit layout.
+    pub fn decode(&self, tsid: TSID) -> TsidComponents {
+        tsid.components(64 - self.random_bits, self.node_bits, self.epoch_millis)
+    }
+
+    fn get_time_and_advance_counter(&mut self) -> Result<u128, TsidGenerationError> {
+        let mut time_millis = self.read_time()?;
+        let mut carried = false;
 
         if time_millis <= self.last_time_value {
+            // Clock is at or behind the last value we used: stay anchored to
+            // `last_time_value` and let the counter carry decide whether we
+            // need to borrow a millisecond from the time field, instead of
+            // adopting the (possibly regressed) clock reading directly.
+            time_millis = self.last_time_value;
             self.counter += 1;
             if self.counter >> self.counter_bits > 0 {
                 //carry
                 time_millis += 1;
+                carried = true;
             }
         } else {
-            self.counter = rng.next_u64();
+            self.counter = self.rng.next_u64();
         }
-        self.counter = self.counter & self.counter_mask;
+        self.counter &= self.counter_mask;
+
+        if time_millis > self.time_mask {
+            return Err(if carried {
+                TsidGenerationError::CounterOverflowWithoutRoom
+            } else {
+                TsidGenerationError::TimeBitsExhausted
+            });
+        }
+
         self.last_time_value = time_millis;
 
-        return time_millis;
+        Ok(time_millis)
     }
 
-    fn get_time_millis_in_tsid_epoch() -> u128 {
-        let tsid_epoch = SystemTime::UNIX_EPOCH.add(Duration::from_millis(TSID_EPOCH_MILLIS));
+    fn read_time(&mut self) -> Result<u128, TsidGenerationError> {
+        loop {
+            let now = self.clock.now_unix_millis();
+            if now < self.epoch_millis {
+                return Err(TsidGenerationError::ClockBehindEpoch);
+            }
+            let time_millis = (now - self.epoch_millis) as u128;
+
+            // Only spin on an actual regression (strictly behind the last
+            // value we used). A same-millisecond reading is the normal case
+            // the per-millisecond counter exists for, not a clock problem;
+            // spinning on it too would cap throughput at ~1 ID/ms and never
+            // let the counter advance.
+            if time_millis < self.last_time_value
+                && self.backwards_clock_policy == BackwardsClockPolicy::SpinWait
+            {
+                continue;
+            }
+            return Ok(time_millis);
+        }
+    }
+}
+
+/// Thread-safe counterpart of [`TsidFactory`].
+///
+/// Instead of requiring `&mut self`, `AtomicTsidFactory` packs `last_time_value`
+/// and `counter` into a single `AtomicU64` and advances both with a
+/// compare-and-swap loop, retrying on contention. This keeps generated IDs
+/// strictly monotonic across threads without a mutex, so a single instance can
+/// be shared (e.g. via `Arc`) across a web server's request handlers.
+///
+/// Hard-coded to [`SystemTime`]/`thread_rng`, so it is only available under
+/// the `std` feature; use [`TsidFactory`] with a custom [`Clock`]/[`RandomSource`]
+/// on `no_std` targets.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AtomicTsidFactory {
+    counter_bits: u8,
+    counter_mask: u64,
+    node: u32,
+    // packed as `(time_millis << RANDOM_BITS) | counter`
+    state: AtomicU64,
+}
 
-        SystemTime::now()
-            .duration_since(tsid_epoch)
-            .expect("UNIX_EPOCH ias after now(), check Your system time")
-            .as_millis()
+#[cfg(feature = "std")]
+impl Default for AtomicTsidFactory {
+    #[doc = "Create default AtomicTsidFactory with `node_bits: 0`"]
+    fn default() -> Self {
+        AtomicTsidFactory::with_node_bits(0, 0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl AtomicTsidFactory {
+    /// Create a new AtomicTsidFactory with default settings
+    /// see [`AtomicTsidFactory::default`]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_node_bits(node_bits: u8, node: u32) -> Self {
+        let counter_bits: u8 = RANDOM_BITS - node_bits;
+        let counter_mask = mask(counter_bits);
+
+        let mut rng = rand::thread_rng();
+        let counter = rng.next_u64() & counter_mask;
+        let time_millis = get_time_millis_in_tsid_epoch()
+            .expect("UNIX_EPOCH is after now(), check Your system time") as u64;
+        let state = (time_millis << RANDOM_BITS) | counter;
+
+        Self {
+            counter_bits,
+            counter_mask,
+            node,
+            state: AtomicU64::new(state),
+        }
+    }
+
+    // lock-free implementation, safe to call from multiple threads concurrently
+    pub fn create(&self) -> Result<TSID, TsidGenerationError> {
+        let mut rng = rand::thread_rng();
+        let time_mask = mask(TIME_BITS);
+
+        loop {
+            let current = self.state.load(Ordering::Acquire);
+            let current_time = current >> RANDOM_BITS;
+            let current_counter = current & self.counter_mask;
+
+            let now_millis = get_time_millis_in_tsid_epoch()? as u64;
+
+            let (new_time, new_counter, carried) = if now_millis <= current_time {
+                let advanced = current_counter + 1;
+                if advanced >> self.counter_bits > 0 {
+                    //carry
+                    (current_time + 1, 0, true)
+                } else {
+                    (current_time, advanced, false)
+                }
+            } else {
+                (now_millis, rng.next_u64() & self.counter_mask, false)
+            };
+
+            if new_time > time_mask {
+                return Err(if carried {
+                    TsidGenerationError::CounterOverflowWithoutRoom
+                } else {
+                    TsidGenerationError::TimeBitsExhausted
+                });
+            }
+
+            let new_state = (new_time << RANDOM_BITS) | new_counter;
+
+            if self
+                .state
+                .compare_exchange(current, new_state, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                let node_val: u64 = (self.node as u64) << self.counter_bits;
+                let time_val: u64 = new_time << RANDOM_BITS;
+                let number = time_val | node_val | new_counter;
+                return Ok(TSID::new(number));
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::factory::{TIME_BITS, TsidFactory};
+    use crate::factory::{BackwardsClockPolicy, RANDOM_BITS, TSID_EPOCH_MILLIS, TsidFactory, TsidGenerationError, mask};
+    #[cfg(feature = "std")]
+    use crate::factory::{AtomicTsidFactory, TIME_BITS};
+    use crate::source::{Clock, RandomSource};
+    use core::cell::Cell;
+
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now_unix_millis(&self) -> u64 {
+            self.0
+        }
+    }
 
+    /// A clock that plays back a fixed sequence of readings, one per call.
+    /// Lets a test script an actual backwards step followed by a recovery, to
+    /// exercise the `SpinWait` retry loop.
+    struct ScriptedClock {
+        readings: &'static [u64],
+        calls: Cell<usize>,
+    }
+
+    impl Clock for ScriptedClock {
+        fn now_unix_millis(&self) -> u64 {
+            let call = self.calls.get();
+            self.calls.set(call + 1);
+            self.readings[call]
+        }
+    }
+
+    struct FixedRng(u64);
+
+    impl RandomSource for FixedRng {
+        fn next_u64(&mut self) -> u64 {
+            self.0
+        }
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn builder_should_set_all_masks_for_8node_bits_version() {
         let factory_under_test = TsidFactory::with_node_bits(8, 0);
@@ -113,10 +383,10 @@ mod tests {
         assert_eq!(8, factory_under_test.node_bits);
         assert_eq!(14, factory_under_test.counter_bits);
         assert_eq!(0x3fff, factory_under_test.counter_mask);
-        assert_eq!(0xff, factory_under_test.node_mask);
         assert_eq!(64, TIME_BITS + factory_under_test.counter_bits + factory_under_test.node_bits)
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn builder_should_set_all_masks_for_0node_bits_version() {
         let factory_under_test = TsidFactory::with_node_bits(0, 0);
@@ -125,14 +395,163 @@ mod tests {
         assert_eq!(0, factory_under_test.node_bits);
         assert_eq!(22, factory_under_test.counter_bits);
         assert_eq!(0x3fffff, factory_under_test.counter_mask);
-        assert_eq!(0x0, factory_under_test.node_mask);
         assert_eq!(64, TIME_BITS + factory_under_test.counter_bits + factory_under_test.node_bits)
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn create_tsid() {
         let mut factory_under_test = TsidFactory::with_node_bits(8, 1);
-        let _tsid = factory_under_test.create();
-        println!("{}", _tsid.to_string())
+        let _tsid = factory_under_test.create().unwrap();
+        println!("{_tsid}")
+    }
+
+    #[test]
+    fn create_tsid_with_fixed_clock_and_rng_is_deterministic() {
+        let mut factory_under_test = TsidFactory::with_node_bits_and_sources(
+            8,
+            1,
+            FixedClock(TSID_EPOCH_MILLIS + 123),
+            FixedRng(0x1234),
+        );
+        let tsid = factory_under_test.create().unwrap();
+        // same millisecond as the initial counter seed, so the counter advances by one
+        assert_eq!(
+            ((123u64) << RANDOM_BITS) | (1 << 14) | (0x1235 & 0x3fff),
+            tsid.number()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn decode_recovers_node_and_counter() {
+        let mut factory_under_test = TsidFactory::with_node_bits(8, 42);
+        let tsid = factory_under_test.create().unwrap();
+
+        let components = factory_under_test.decode(tsid);
+        assert_eq!(42, components.node);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn create_rejects_clock_behind_epoch() {
+        let mut factory_under_test = TsidFactory::with_node_bits_and_sources(
+            8,
+            1,
+            FixedClock(TSID_EPOCH_MILLIS - 1),
+            FixedRng(0x1234),
+        );
+        assert_eq!(
+            Err(TsidGenerationError::ClockBehindEpoch),
+            factory_under_test.create()
+        );
+    }
+
+    #[test]
+    fn create_rejects_time_bits_exhausted() {
+        let time_bits = 8;
+        let over_ceiling = TSID_EPOCH_MILLIS + mask(time_bits) + 1;
+        let mut factory_under_test =
+            TsidFactory::from_parts(time_bits, 8, 48, TSID_EPOCH_MILLIS, 1, FixedClock(over_ceiling), FixedRng(0));
+        assert_eq!(
+            Err(TsidGenerationError::TimeBitsExhausted),
+            factory_under_test.create()
+        );
+    }
+
+    #[test]
+    fn bump_sequence_policy_keeps_ids_monotonic_when_clock_regresses() {
+        let mut factory_under_test = TsidFactory::with_node_bits_and_sources(
+            8,
+            1,
+            FixedClock(TSID_EPOCH_MILLIS + 100),
+            FixedRng(0),
+        );
+        let first = factory_under_test.create().unwrap();
+
+        factory_under_test.clock = FixedClock(TSID_EPOCH_MILLIS + 50);
+        let second = factory_under_test.create().unwrap();
+
+        assert!(second > first);
+    }
+
+    #[test]
+    fn spin_wait_policy_retries_on_regression() {
+        // Construction reads 100. The next `create` then sees two regressed
+        // readings (50, 50) that SpinWait must retry past, before a third
+        // reading (150) that is ahead and gets accepted.
+        let clock = ScriptedClock {
+            readings: &[TSID_EPOCH_MILLIS + 100, TSID_EPOCH_MILLIS + 50, TSID_EPOCH_MILLIS + 50, TSID_EPOCH_MILLIS + 150],
+            calls: Cell::new(0),
+        };
+        let mut factory_under_test = TsidFactory::with_node_bits_and_sources(8, 1, clock, FixedRng(0))
+            .with_backwards_clock_policy(BackwardsClockPolicy::SpinWait);
+
+        let tsid = factory_under_test.create().unwrap();
+        let components = factory_under_test.decode(tsid);
+        // The regressed readings were spun past rather than adopted, and the
+        // recovered reading (150) was used directly instead of anchoring to
+        // the last-used value (100).
+        assert_eq!(TSID_EPOCH_MILLIS + 150, components.timestamp_millis);
+    }
+
+    #[test]
+    fn spin_wait_policy_does_not_spin_on_same_millisecond_reading() {
+        // A clock that repeats the same reading forever would hang a naive
+        // `<=`-triggered spin loop; this must return immediately and let the
+        // counter advance instead.
+        let clock = FixedClock(TSID_EPOCH_MILLIS + 100);
+        let mut factory_under_test = TsidFactory::with_node_bits_and_sources(8, 1, clock, FixedRng(0))
+            .with_backwards_clock_policy(BackwardsClockPolicy::SpinWait);
+
+        let first = factory_under_test.create().unwrap();
+        let second = factory_under_test.create().unwrap();
+        assert!(second > first);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn atomic_builder_should_set_all_masks_for_8node_bits_version() {
+        let node_bits = 8;
+        let factory_under_test = AtomicTsidFactory::with_node_bits(node_bits, 0);
+        println!("{:?}", factory_under_test);
+
+        assert_eq!(14, factory_under_test.counter_bits);
+        assert_eq!(0x3fff, factory_under_test.counter_mask);
+        assert_eq!(64, TIME_BITS + factory_under_test.counter_bits + node_bits)
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn atomic_create_tsid() {
+        let factory_under_test = AtomicTsidFactory::with_node_bits(8, 1);
+        let _tsid = factory_under_test.create().unwrap();
+        println!("{_tsid}")
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn atomic_create_tsid_is_monotonic_across_threads() {
+        use std::sync::Arc;
+
+        let factory_under_test = Arc::new(AtomicTsidFactory::with_node_bits(8, 1));
+        let mut handles = Vec::new();
+
+        for _ in 0..8 {
+            let factory = Arc::clone(&factory_under_test);
+            handles.push(std::thread::spawn(move || {
+                let ids = (0..1000)
+                    .map(|_| factory.create().unwrap().to_string())
+                    .collect::<Vec<_>>();
+                // each thread sees its own IDs strictly increasing
+                assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+                ids
+            }));
+        }
+
+        let mut all_ids: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        let total = all_ids.len();
+        all_ids.sort();
+        all_ids.dedup();
+        assert_eq!(total, all_ids.len());
+    }
+}
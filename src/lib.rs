@@ -0,0 +1,15 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod factory;
+mod source;
+mod tsid;
+
+pub use factory::{BackwardsClockPolicy, TsidFactory, TsidFactoryBuildError, TsidFactoryBuilder, TsidGenerationError};
+#[cfg(feature = "std")]
+pub use factory::AtomicTsidFactory;
+pub use source::{Clock, RandomSource};
+#[cfg(feature = "std")]
+pub use source::{SystemClock, ThreadRng};
+pub use tsid::{TSID, TsidComponents, TsidParseError};
@@ -0,0 +1,49 @@
+//! Pluggable clock and randomness sources used by [`crate::TsidFactory`].
+//!
+//! These traits let `TsidFactory` run on targets without `std` (e.g. a
+//! hardware tick counter on an embedded target) and let tests inject a fixed
+//! clock to assert exact generated values. The `std`-backed implementations
+//! below preserve the crate's previous default behavior.
+
+/// A source of milliseconds elapsed since the Unix epoch.
+///
+/// `TsidFactory` subtracts its own configured epoch from this value, so a
+/// single `Clock` impl works no matter which epoch a factory is built with.
+pub trait Clock {
+    fn now_unix_millis(&self) -> u64;
+}
+
+/// A source of random 64-bit values, used to seed/advance the counter.
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// [`Clock`] backed by [`std::time::SystemTime`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_unix_millis(&self) -> u64 {
+        use std::time::SystemTime;
+
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("UNIX_EPOCH is after now(), check Your system time")
+            .as_millis() as u64
+    }
+}
+
+/// [`RandomSource`] backed by [`rand::thread_rng`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadRng;
+
+#[cfg(feature = "std")]
+impl RandomSource for ThreadRng {
+    fn next_u64(&mut self) -> u64 {
+        use rand::RngCore;
+        rand::thread_rng().next_u64()
+    }
+}
@@ -0,0 +1,251 @@
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::factory::mask;
+
+const TSID_STRING_LEN: usize = 13;
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TSID {
+    number: u64,
+}
+
+impl TSID {
+    pub fn new(number: u64) -> Self {
+        Self { number }
+    }
+
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Encode as the canonical 13-character Crockford base32 string.
+    ///
+    /// The output is most-significant-group first and zero-padded, so
+    /// lexicographic ordering of the string matches numeric (and therefore
+    /// chronological) ordering of the underlying 64-bit value.
+    pub fn to_base32(&self) -> String {
+        let number = self.number;
+        let chars = [
+            CROCKFORD_ALPHABET[((number >> 60) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 55) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 50) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 45) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 40) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 35) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 30) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 25) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 20) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 15) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 10) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[((number >> 5) & 0x1f) as usize],
+            CROCKFORD_ALPHABET[(number & 0x1f) as usize],
+        ];
+        // SAFETY: every entry comes from CROCKFORD_ALPHABET, which is ASCII.
+        String::from_utf8(chars.to_vec()).unwrap()
+    }
+
+    /// Parse a Crockford base32 string produced by [`TSID::to_base32`].
+    ///
+    /// Accepts both upper and lowercase input and applies the Crockford
+    /// ambiguity mappings (`I`/`L` -> `1`, `O` -> `0`).
+    pub fn from_base32(input: &str) -> Result<TSID, TsidParseError> {
+        let chars: Vec<char> = input.chars().collect();
+        if chars.len() != TSID_STRING_LEN {
+            return Err(TsidParseError::InvalidLength(chars.len()));
+        }
+
+        let mut number: u64 = 0;
+        for (i, c) in chars.iter().enumerate() {
+            let value = decode_char(*c).ok_or(TsidParseError::InvalidCharacter(*c))?;
+            if i == 0 && value > 0x0f {
+                // the first group only has 4 valid bits (13 * 5 == 65 > 64)
+                return Err(TsidParseError::Overflow);
+            }
+            number = (number << 5) | value as u64;
+        }
+
+        Ok(TSID::new(number))
+    }
+
+    /// Split this TSID back into its timestamp, node, and counter, given the
+    /// `time_bits`/`node_bits` split and `epoch_millis` (milliseconds since
+    /// the Unix epoch) that were used to create it. These are configurable
+    /// per factory (see [`crate::TsidFactoryBuilder`]), so they can't be
+    /// recovered from the TSID alone; prefer [`crate::TsidFactory::decode`]
+    /// when you still have the factory that created it.
+    pub fn components(&self, time_bits: u8, node_bits: u8, epoch_millis: u64) -> TsidComponents {
+        let random_bits = 64 - time_bits;
+        let counter_bits = random_bits - node_bits;
+        let counter_mask = mask(counter_bits);
+        let node_mask = mask(node_bits);
+
+        let millis_since_epoch = self.number >> random_bits;
+        let node = ((self.number >> counter_bits) & node_mask) as u32;
+        let counter = (self.number & counter_mask) as u32;
+
+        TsidComponents {
+            timestamp_millis: epoch_millis + millis_since_epoch,
+            node,
+            counter,
+        }
+    }
+}
+
+/// The timestamp, node, and counter a [`TSID`] was built from.
+///
+/// See [`TSID::components`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TsidComponents {
+    /// Milliseconds since the Unix epoch. Always available, even without the
+    /// `std` feature; see [`TsidComponents::timestamp`] for a `SystemTime`.
+    pub timestamp_millis: u64,
+    pub node: u32,
+    pub counter: u32,
+}
+
+#[cfg(feature = "std")]
+impl TsidComponents {
+    /// [`Self::timestamp_millis`] as a [`SystemTime`].
+    pub fn timestamp(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(self.timestamp_millis)
+    }
+}
+
+fn decode_char(c: char) -> Option<u8> {
+    let normalized = match c.to_ascii_uppercase() {
+        'I' | 'L' => '1',
+        'O' => '0',
+        other => other,
+    };
+    CROCKFORD_ALPHABET
+        .iter()
+        .position(|&b| b as char == normalized)
+        .map(|position| position as u8)
+}
+
+impl fmt::Display for TSID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_base32())
+    }
+}
+
+impl FromStr for TSID {
+    type Err = TsidParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TSID::from_base32(s)
+    }
+}
+
+/// Errors returned when parsing a TSID from its base32 string form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsidParseError {
+    /// The input did not have the expected length of 13 characters.
+    InvalidLength(usize),
+    /// The input contained a character outside the Crockford base32 alphabet.
+    InvalidCharacter(char),
+    /// The input decodes to a value wider than 64 bits.
+    Overflow,
+}
+
+impl fmt::Display for TsidParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TsidParseError::InvalidLength(len) => {
+                write!(f, "expected a {TSID_STRING_LEN}-character string, got {len}")
+            }
+            TsidParseError::InvalidCharacter(c) => write!(f, "invalid base32 character: {c:?}"),
+            TsidParseError::Overflow => write!(f, "value does not fit in 64 bits"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TsidParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base32_round_trip() {
+        let tsid = TSID::new(0x0123_4567_89ab_cdef);
+        let encoded = tsid.to_base32();
+        assert_eq!(13, encoded.len());
+        assert_eq!(tsid, TSID::from_base32(&encoded).unwrap());
+    }
+
+    #[test]
+    fn base32_is_zero_padded_and_sortable() {
+        let smaller = TSID::new(1).to_base32();
+        let larger = TSID::new(2).to_base32();
+        assert_eq!(13, smaller.len());
+        assert!(smaller < larger);
+    }
+
+    #[test]
+    fn from_base32_accepts_lowercase_and_ambiguous_characters() {
+        let tsid = TSID::new(0x0123_4567_89ab_cdef);
+        let encoded = tsid.to_base32().to_lowercase();
+        assert_eq!(tsid, TSID::from_base32(&encoded).unwrap());
+        // o/O -> 0, l/I -> 1
+        assert_eq!(TSID::new(33), TSID::from_base32("000000000oOlI").unwrap());
+    }
+
+    #[test]
+    fn from_base32_rejects_wrong_length() {
+        assert_eq!(
+            Err(TsidParseError::InvalidLength(3)),
+            TSID::from_base32("abc")
+        );
+    }
+
+    #[test]
+    fn from_base32_rejects_invalid_character() {
+        assert_eq!(
+            Err(TsidParseError::InvalidCharacter('U')),
+            TSID::from_base32("000000000000U")
+        );
+    }
+
+    #[test]
+    fn components_recovers_node_and_counter() {
+        let time_bits = 42;
+        let node_bits = 8;
+        let random_bits = 64 - time_bits;
+        let counter_bits = random_bits - node_bits;
+        let counter_mask = mask(counter_bits);
+        let node_mask = mask(node_bits);
+        let epoch_millis = 1_577_836_800_000;
+
+        let time: u64 = 987_654;
+        let node: u64 = 0x2a & node_mask;
+        let counter: u64 = 0x3ff & counter_mask;
+        let tsid = TSID::new((time << random_bits) | (node << counter_bits) | counter);
+
+        let components = tsid.components(time_bits, node_bits, epoch_millis);
+        assert_eq!(node as u32, components.node);
+        assert_eq!(counter as u32, components.counter);
+        assert_eq!(epoch_millis + time, components.timestamp_millis);
+        #[cfg(feature = "std")]
+        assert_eq!(
+            SystemTime::UNIX_EPOCH + Duration::from_millis(epoch_millis + time),
+            components.timestamp()
+        );
+    }
+
+    #[test]
+    fn from_base32_rejects_overflow() {
+        assert_eq!(
+            Err(TsidParseError::Overflow),
+            TSID::from_base32("Z000000000000")
+        );
+    }
+}